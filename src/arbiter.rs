@@ -6,20 +6,73 @@ pub trait Arbitratable: Clone {
     fn seq_num(&self) -> u64;
     /// Returns the identifier of the source line this message came from.
     fn source_line(&self) -> u8;
+    /// Returns the microsecond timestamp at which the message was produced/received.
+    fn timestamp(&self) -> u64;
 }
 
 #[derive(Debug)]
 pub struct Arbiter<T: Arbitratable> {
     latest_inorder_seq_num: u64, // Track the latest sequence number seen
     latest_seq_nums: Vec<u64>, // Track the latest sequence number seen per line
-    buffer: BTreeMap<u64, T>, // Use a BTreeMap to automatically handle sorting and prevent duplicates by sequence number.
+    line_last_seen: Vec<u64>, // Track the last time (us) a message arrived on each line, for liveness tracking.
+    buffer: BTreeMap<u64, (T, u64)>, // Use a BTreeMap to automatically handle sorting and prevent duplicates by sequence number. Value is paired with the message's arrival timestamp for latency-based expiry.
     unrecoverable_threshold: u64, // If all lines have passed the gap over this threshold, we consider the gap as lost.
+    latency_us: u64, // Maximum time a gap may remain outstanding before `tick` declares it lost.
+    line_timeout_us: u64, // Lines silent longer than this are excluded from the unrecoverable-gap quorum.
+    max_buffer_len: usize, // Caps reorder-buffer growth; a full buffer force-resolves the oldest gap.
+    stats: ArbiterStats, // Cumulative observability counters; see `Arbiter::stats`.
+}
+
+/// Observability counters for an `Arbiter`, exposed via `Arbiter::stats`.
+///
+/// All fields besides `buffer_occupancy` are cumulative since the last `reset_stats`
+/// call (or since the arbiter was created); `buffer_occupancy` is a live gauge.
+#[derive(Debug, Clone, Default)]
+pub struct ArbiterStats {
+    pub total_received: u64,
+    pub received_per_line: Vec<u64>,
+    pub duplicates_discarded: u64,
+    pub stale_discarded: u64,
+    pub buffered_for_reorder: u64,
+    pub gaps_opened: u64,
+    pub gaps_filled: u64,
+    pub gaps_lost: u64,
+    pub buffer_occupancy: usize,
+    pub max_reorder_depth: u64,
+}
+
+/// Reports that the reorder buffer was full and the oldest gap had to be force-resolved
+/// to make room, analogous to a broadcast receiver's lag count.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Lagged {
+    pub skipped: u64,
+}
+
+/// A snapshot of a single source line's liveness, as reported by `Arbiter::line_states`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct LineState {
+    pub line: u8,
+    pub latest_seq_num: u64,
+    pub last_seen_us: u64,
 }
 
 #[derive(Debug, PartialEq, Eq)]
 pub enum ArbiterError {
     OutOfBoundsSourceLine,
-    UnrecoverableGap,
+}
+
+/// The result of feeding a message into the arbiter.
+///
+/// `messages` are the in-order, de-duplicated messages released by this call.
+/// `lost` lists sequence numbers that were declared permanently unrecoverable and
+/// skipped over in the process, e.g. when every line has passed a gap that can
+/// therefore never be filled. `lagged` is set when the reorder buffer was full and
+/// the oldest gap had to be force-resolved to make room for this message.
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct Delivery<T> {
+    pub messages: Vec<T>,
+    pub lost: Vec<u64>,
+    pub lagged: Option<Lagged>,
 }
 
 impl<T: Arbitratable> Arbiter<T> {
@@ -28,17 +81,38 @@ impl<T: Arbitratable> Arbiter<T> {
     /// * `num_lines`: The total number of source lines to track.
     /// * `unrecoverable_threshold`: The number of messages past a gap for all lines
     ///   before the gap is considered unrecoverable.
-    pub fn new(num_lines: usize, unrecoverable_threshold: u64) -> Self {
+    /// * `latency_us`: The maximum time, in microseconds, a gap may remain outstanding
+    ///   before `tick` gives up on it.
+    /// * `line_timeout_us`: The maximum time, in microseconds, a line may go silent
+    ///   before it's excluded from the unrecoverable-gap quorum.
+    /// * `max_buffer_len`: The maximum number of messages the reorder buffer may hold
+    ///   before the oldest gap is force-resolved to make room.
+    pub fn new(
+        num_lines: usize,
+        unrecoverable_threshold: u64,
+        latency_us: u64,
+        line_timeout_us: u64,
+        max_buffer_len: usize,
+    ) -> Self {
         Arbiter {
             latest_inorder_seq_num: 0,
             latest_seq_nums: vec![0; num_lines],
+            line_last_seen: vec![0; num_lines],
             buffer: BTreeMap::new(),
             unrecoverable_threshold,
+            latency_us,
+            line_timeout_us,
+            max_buffer_len,
+            stats: ArbiterStats {
+                received_per_line: vec![0; num_lines],
+                ..Default::default()
+            },
         }
     }
 
-    pub fn receive_message(&mut self, msg: T) -> Result<Vec<T>, ArbiterError> {
+    pub fn receive_message(&mut self, msg: T) -> Result<Delivery<T>, ArbiterError> {
         let mut return_messages = vec![];
+        let mut lagged = None;
 
         // Prevent panic by checking if source_line is valid.
         let line_idx = msg.source_line() as usize;
@@ -46,26 +120,93 @@ impl<T: Arbitratable> Arbiter<T> {
             return Err(ArbiterError::OutOfBoundsSourceLine);
         }
 
+        self.stats.total_received += 1;
+        self.stats.received_per_line[line_idx] += 1;
+
         // Correctly update the latest sequence number for the source line.
         self.latest_seq_nums[line_idx] = self.latest_seq_nums[line_idx].max(msg.seq_num());
+        // Record the line as alive at this message's arrival time.
+        let now_us = msg.timestamp();
+        self.line_last_seen[line_idx] = self.line_last_seen[line_idx].max(now_us);
 
         if msg.seq_num() == self.latest_inorder_seq_num + 1 {
             // Case 1: In-order message.
             self.latest_inorder_seq_num = msg.seq_num();
             return_messages.push(msg);
             // After accepting an in-order message, try to process the buffer.
-            return_messages.extend(self.process_buffer());
+            let drained = self.process_buffer();
+            if !drained.is_empty() {
+                self.stats.gaps_filled += 1;
+            }
+            return_messages.extend(drained);
+        } else if msg.seq_num() > self.latest_inorder_seq_num + 1 && self.max_buffer_len == 0 {
+            // Buffering is disabled entirely: there's no room to hold this message, so
+            // treat it as the fill point for the open gap, sacrificing everything in
+            // between instead of calling `force_resolve_oldest_gap` on an empty buffer.
+            let skipped = msg.seq_num() - self.latest_inorder_seq_num - 1;
+            self.latest_inorder_seq_num = msg.seq_num();
+            self.stats.gaps_lost += 1;
+            lagged = Some(Lagged { skipped });
+            return_messages.push(msg);
         } else if msg.seq_num() > self.latest_inorder_seq_num + 1 {
             // Case 2: Future message (gap detected).
-            self.buffer.insert(msg.seq_num(), msg);
+            let is_new_entry = !self.buffer.contains_key(&msg.seq_num());
+            if is_new_entry && self.buffer.is_empty() {
+                self.stats.gaps_opened += 1;
+            }
+
+            if is_new_entry && self.buffer.len() >= self.max_buffer_len {
+                // No room left; sacrifice the oldest gap to make space for this message.
+                let (recovered, skipped) = self.force_resolve_oldest_gap();
+                if skipped > 0 {
+                    self.stats.gaps_lost += 1;
+                }
+                return_messages.extend(recovered);
+                lagged = Some(Lagged { skipped });
+            }
+
+            if msg.seq_num() <= self.latest_inorder_seq_num {
+                // The eviction above advanced the in-order mark past this message's own
+                // seq_num (it arrived lower than everything else that was buffered), so
+                // it's now stale/duplicate rather than something we can still buffer.
+                if msg.seq_num() == self.latest_inorder_seq_num {
+                    self.stats.duplicates_discarded += 1;
+                } else {
+                    self.stats.stale_discarded += 1;
+                }
+            } else {
+                if is_new_entry {
+                    self.stats.buffered_for_reorder += 1;
+                    let reorder_depth = msg.seq_num() - self.latest_inorder_seq_num;
+                    self.stats.max_reorder_depth = self.stats.max_reorder_depth.max(reorder_depth);
+                } else {
+                    self.stats.duplicates_discarded += 1;
+                }
+
+                let arrival_ts = msg.timestamp();
+                self.buffer.insert(msg.seq_num(), (msg, arrival_ts));
+            }
+        } else if msg.seq_num() == self.latest_inorder_seq_num {
+            // Case 3a: Duplicate of the message we just delivered. Discard it.
+            self.stats.duplicates_discarded += 1;
         } else {
-            // Case 3: Stale or duplicate message. Discard it.
+            // Case 3b: Stale message, older than anything we'd still deliver. Discard it.
+            self.stats.stale_discarded += 1;
         }
 
-        // Check if any gaps can now be considered unrecoverable.
-        self.check_gaps()?;
+        // If every live line has now passed the current gap, it can never be filled;
+        // skip over it instead of leaving the arbiter stuck forever.
+        let (recovered, lost) = self.resolve_unrecoverable_gap(now_us);
+        if !lost.is_empty() {
+            self.stats.gaps_lost += 1;
+        }
+        return_messages.extend(recovered);
 
-        Ok(return_messages)
+        Ok(Delivery {
+            messages: return_messages,
+            lost,
+            lagged,
+        })
     }
 
     /// Processes buffered messages that are now in-order.
@@ -73,11 +214,11 @@ impl<T: Arbitratable> Arbiter<T> {
         let mut return_messages = vec![];
 
         while let Some(kp) = self.buffer.first_entry() {
-            let msg = kp.get();
+            let (msg, _) = kp.get();
             if msg.seq_num() == self.latest_inorder_seq_num + 1 {
                 // This message is the one we were waiting for.
                 // `pop_first` is efficient and safe to unwrap since we just checked that the entry exists.
-                let (_, val) = self.buffer.pop_first().unwrap();
+                let (_, (val, _)) = self.buffer.pop_first().unwrap();
                 self.latest_inorder_seq_num = val.seq_num();
                 return_messages.push(val);
             } else {
@@ -85,28 +226,132 @@ impl<T: Arbitratable> Arbiter<T> {
                 break;
             }
         }
-        
+
+        return_messages
+    }
+
+    /// Expires the gap immediately ahead of `latest_inorder_seq_num` once it has been
+    /// outstanding longer than `latency_us`.
+    ///
+    /// If the earliest buffered message beyond the gap arrived more than `latency_us`
+    /// microseconds before `now_us`, the missing sequence numbers are declared lost,
+    /// `latest_inorder_seq_num` is advanced up to that message, and the buffer is
+    /// flushed via `process_buffer`. Repeats for as many consecutive gaps as have
+    /// expired by `now_us` in a single call.
+    pub fn tick(&mut self, now_us: u64) -> Vec<T> {
+        let mut return_messages = vec![];
+
+        while let Some((&seq_num, &(_, arrival_ts))) = self.buffer.first_key_value() {
+            if seq_num <= self.latest_inorder_seq_num {
+                // Left over from a prior expiry; drop and keep scanning.
+                self.buffer.pop_first();
+                continue;
+            }
+            if now_us.saturating_sub(arrival_ts) <= self.latency_us {
+                // The current gap hasn't been outstanding long enough yet.
+                break;
+            }
+
+            // Abandon the gap: pretend everything up to `seq_num` arrived.
+            self.stats.gaps_lost += 1;
+            self.latest_inorder_seq_num = seq_num - 1;
+            return_messages.extend(self.process_buffer());
+        }
+
         return_messages
     }
 
-    /// Checks if a gap can be considered unrecoverable.
-    fn check_gaps(&self) -> Result<(), ArbiterError> {
+    /// Skips the current gap if every live line has passed it by at least
+    /// `unrecoverable_threshold`, since it can then never be filled.
+    ///
+    /// Lines that haven't been heard from in more than `line_timeout_us` are excluded
+    /// from the quorum, so a single crashed or disconnected line can't pin the gap
+    /// forever. Advances `latest_inorder_seq_num` to the next buffered sequence number
+    /// and flushes the now-contiguous buffer via `process_buffer`, returning the
+    /// released messages alongside the sequence numbers that were declared permanently
+    /// lost. Does nothing if the buffer is empty, there are no live lines, or the gap
+    /// hasn't been passed by every live line.
+    fn resolve_unrecoverable_gap(&mut self, now_us: u64) -> (Vec<T>, Vec<u64>) {
         // If the buffer is empty, there are no gaps to check.
         if self.buffer.is_empty() {
-            return Ok(());
+            return (vec![], vec![]);
         }
 
         let gap_seq_num = self.latest_inorder_seq_num + 1;
 
-        // Check if all lines have advanced far enough past the current gap.
-        let all_lines_passed_gap = self.latest_seq_nums
+        // Check if all live lines have advanced far enough past the current gap.
+        let mut any_alive = false;
+        let all_live_lines_passed_gap = self.latest_seq_nums
+            .iter()
+            .enumerate()
+            .filter(|&(line, _)| self.is_line_alive(line as u8, now_us))
+            .inspect(|_| any_alive = true)
+            .all(|(_, &seq_num)| seq_num >= gap_seq_num + self.unrecoverable_threshold);
+
+        if !any_alive || !all_live_lines_passed_gap {
+            return (vec![], vec![]);
+        }
+
+        // The gap can never be filled. Skip straight to the next buffered message.
+        let next_seq_num = *self.buffer.first_key_value().unwrap().0;
+        let lost: Vec<u64> = (gap_seq_num..next_seq_num).collect();
+        self.latest_inorder_seq_num = next_seq_num - 1;
+
+        (self.process_buffer(), lost)
+    }
+
+    /// Force-resolves the gap immediately ahead of `latest_inorder_seq_num` to make
+    /// room in a full reorder buffer.
+    ///
+    /// Advances `latest_inorder_seq_num` up to the earliest buffered message and
+    /// flushes the now-contiguous buffer via `process_buffer`, returning the released
+    /// messages alongside the number of sequence numbers sacrificed to make room.
+    /// Only called when the buffer is non-empty.
+    fn force_resolve_oldest_gap(&mut self) -> (Vec<T>, u64) {
+        let next_seq_num = *self.buffer.first_key_value().expect("buffer is full, so non-empty").0;
+        let skipped = next_seq_num - self.latest_inorder_seq_num - 1;
+        self.latest_inorder_seq_num = next_seq_num - 1;
+
+        (self.process_buffer(), skipped)
+    }
+
+    /// Returns whether `line` has been heard from within the last `line_timeout_us`
+    /// microseconds as of `now_us`. Out-of-bounds lines are never considered alive.
+    pub fn is_line_alive(&self, line: u8, now_us: u64) -> bool {
+        match self.line_last_seen.get(line as usize) {
+            Some(&last_seen_us) => now_us.saturating_sub(last_seen_us) <= self.line_timeout_us,
+            None => false,
+        }
+    }
+
+    /// Reports the latest sequence number and last-seen time for every source line.
+    pub fn line_states(&self) -> Vec<LineState> {
+        self.latest_seq_nums
             .iter()
-            .all(|&seq_num| seq_num >= gap_seq_num + self.unrecoverable_threshold);
+            .zip(self.line_last_seen.iter())
+            .enumerate()
+            .map(|(line, (&latest_seq_num, &last_seen_us))| LineState {
+                line: line as u8,
+                latest_seq_num,
+                last_seen_us,
+            })
+            .collect()
+    }
 
-        if all_lines_passed_gap {
-            return Err(ArbiterError::UnrecoverableGap);
+    /// Returns a snapshot of the arbiter's observability counters.
+    pub fn stats(&self) -> ArbiterStats {
+        ArbiterStats {
+            buffer_occupancy: self.buffer.len(),
+            ..self.stats.clone()
         }
+    }
 
-        Ok(())
+    /// Resets all cumulative counters to zero. The live `buffer_occupancy` gauge is
+    /// unaffected, since it isn't cumulative.
+    pub fn reset_stats(&mut self) {
+        self.stats = ArbiterStats {
+            received_per_line: vec![0; self.stats.received_per_line.len()],
+            ..Default::default()
+        };
     }
 }
\ No newline at end of file
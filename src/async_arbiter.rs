@@ -0,0 +1,61 @@
+use tokio::sync::{broadcast, Mutex};
+
+use crate::arbiter::{Arbiter, Arbitratable, ArbiterError};
+
+/// Async front-end around `Arbiter` that fans the in-order, de-duplicated stream out
+/// to multiple independent subscribers using a tokio broadcast channel.
+///
+/// Each subscriber is backed by its own bounded ring buffer; a subscriber that falls
+/// behind observes a `RecvError::Lagged(n)` the next time it polls, rather than
+/// stalling the producer or any other subscriber.
+pub struct AsyncArbiter<T: Arbitratable + Send + Sync + 'static> {
+    inner: Mutex<Arbiter<T>>,
+    sender: broadcast::Sender<T>,
+}
+
+impl<T: Arbitratable + Send + Sync + 'static> AsyncArbiter<T> {
+    /// Creates a new AsyncArbiter.
+    ///
+    /// * `channel_capacity`: The number of messages each subscriber's ring buffer can
+    ///   hold before it starts lagging. See `Arbiter::new` for the remaining parameters.
+    pub fn new(
+        num_lines: usize,
+        unrecoverable_threshold: u64,
+        latency_us: u64,
+        line_timeout_us: u64,
+        max_buffer_len: usize,
+        channel_capacity: usize,
+    ) -> Self {
+        let (sender, _) = broadcast::channel(channel_capacity);
+        AsyncArbiter {
+            inner: Mutex::new(Arbiter::new(
+                num_lines,
+                unrecoverable_threshold,
+                latency_us,
+                line_timeout_us,
+                max_buffer_len,
+            )),
+            sender,
+        }
+    }
+
+    /// Feeds `msg` into the inner arbiter and publishes every released message to all
+    /// current subscribers.
+    pub async fn push(&self, msg: T) -> Result<(), ArbiterError> {
+        let delivery = self.inner.lock().await.receive_message(msg)?;
+
+        for released in delivery.messages {
+            // A send error just means there are currently no subscribers; the arbiter
+            // itself must keep making progress regardless.
+            let _ = self.sender.send(released);
+        }
+
+        Ok(())
+    }
+
+    /// Returns an independent handle that receives every message this arbiter releases
+    /// from this point on.
+    pub fn subscribe(&self) -> broadcast::Receiver<T> {
+        self.sender.subscribe()
+    }
+}
@@ -15,7 +15,6 @@ fn main() {
 
     // Initialize the Arbiter. Because Arbiter is generic over the message type,
     // we must tell the compiler which concrete type it will be handling.
-    // let arbiter: Arbiter<Message> = Arbiter::new(3, 5);
-    let arbiter: Arbiter<Message> = Arbiter::new(3, 5);
+    let arbiter: Arbiter<Message> = Arbiter::new(3, 5, 50_000, 1_000_000, 1024);
     println!("Initial arbiter state: {:?}", arbiter);
 }
\ No newline at end of file
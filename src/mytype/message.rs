@@ -35,4 +35,8 @@ impl Arbitratable for Message {
     fn source_line(&self) -> u8 {
         self.source_line
     }
+
+    fn timestamp(&self) -> u64 {
+        self.ts
+    }
 }
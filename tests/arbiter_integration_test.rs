@@ -1,7 +1,7 @@
 // Since this is an integration test in the `tests` directory, it's treated
 // as an external user of the library. We need to import the public items
 // from our `line_arbitration` crate.
-use line_arbitration::arbiter::{Arbiter, ArbiterError};
+use line_arbitration::arbiter::{Arbiter, ArbiterError, Lagged};
 use line_arbitration::mytype::message::Message;
 
 // Helper function to create messages for tests
@@ -9,156 +9,329 @@ fn msg(seq_num: u64, source_line: u8) -> Message {
     Message::new(seq_num, source_line, 0, vec![])
 }
 
+// Helper function to create messages with an explicit arrival timestamp.
+fn msg_at(seq_num: u64, source_line: u8, ts: u64) -> Message {
+    Message::new(seq_num, source_line, ts, vec![])
+}
+
 #[test]
 fn test_in_order_messages() {
-    let mut arbiter = Arbiter::new(2, 5);
-    let messages = arbiter.receive_message(msg(1, 0)).unwrap();
-    assert_eq!(messages.len(), 1);
-    assert_eq!(messages[0].seq_num, 1);
-
-    let messages = arbiter.receive_message(msg(2, 1)).unwrap();
-    assert_eq!(messages.len(), 1);
-    assert_eq!(messages[0].seq_num, 2);
+    let mut arbiter = Arbiter::new(2, 5, 1_000_000, 1_000_000, 1024);
+    let delivery = arbiter.receive_message(msg(1, 0)).unwrap();
+    assert_eq!(delivery.messages.len(), 1);
+    assert_eq!(delivery.messages[0].seq_num, 1);
+
+    let delivery = arbiter.receive_message(msg(2, 1)).unwrap();
+    assert_eq!(delivery.messages.len(), 1);
+    assert_eq!(delivery.messages[0].seq_num, 2);
 }
 
 #[test]
 fn test_out_of_order_buffering_and_gap_filling() {
-    let mut arbiter = Arbiter::new(2, 5);
+    let mut arbiter = Arbiter::new(2, 5, 1_000_000, 1_000_000, 1024);
     // Message 3 arrives, but 1 and 2 are missing. Should be buffered.
-    let messages = arbiter.receive_message(msg(3, 0)).unwrap();
-    assert!(messages.is_empty(), "Should buffer message 3 and return nothing");
+    let delivery = arbiter.receive_message(msg(3, 0)).unwrap();
+    assert!(delivery.messages.is_empty(), "Should buffer message 3 and return nothing");
 
     // Message 2 arrives, also buffered.
-    let messages = arbiter.receive_message(msg(2, 1)).unwrap();
-    assert!(messages.is_empty(), "Should buffer message 2 and return nothing");
+    let delivery = arbiter.receive_message(msg(2, 1)).unwrap();
+    assert!(delivery.messages.is_empty(), "Should buffer message 2 and return nothing");
 
     // Send 1, filling the gap.
-    let messages = arbiter.receive_message(msg(1, 0)).unwrap();
+    let delivery = arbiter.receive_message(msg(1, 0)).unwrap();
     // Should receive 1, 2, and 3 in order.
-    assert_eq!(messages.len(), 3, "Should return the complete sequence");
-    assert_eq!(messages[0].seq_num, 1);
-    assert_eq!(messages[1].seq_num, 2);
-    assert_eq!(messages[2].seq_num, 3);
+    assert_eq!(delivery.messages.len(), 3, "Should return the complete sequence");
+    assert_eq!(delivery.messages[0].seq_num, 1);
+    assert_eq!(delivery.messages[1].seq_num, 2);
+    assert_eq!(delivery.messages[2].seq_num, 3);
 }
 
 #[test]
 fn test_stale_and_duplicate_messages() {
-    let mut arbiter = Arbiter::new(2, 5);
+    let mut arbiter = Arbiter::new(2, 5, 1_000_000, 1_000_000, 1024);
     arbiter.receive_message(msg(1, 0)).unwrap();
     arbiter.receive_message(msg(2, 1)).unwrap();
 
     // Stale message
-    let messages = arbiter.receive_message(msg(1, 0)).unwrap();
-    assert!(messages.is_empty(), "Should discard stale message 1");
+    let delivery = arbiter.receive_message(msg(1, 0)).unwrap();
+    assert!(delivery.messages.is_empty(), "Should discard stale message 1");
 
     // Duplicate message (already processed)
-    let messages = arbiter.receive_message(msg(2, 1)).unwrap();
-    assert!(messages.is_empty(), "Should discard duplicate message 2");
+    let delivery = arbiter.receive_message(msg(2, 1)).unwrap();
+    assert!(delivery.messages.is_empty(), "Should discard duplicate message 2");
 
     // Stale message (seq_num 0)
-    let messages = arbiter.receive_message(msg(0, 0)).unwrap();
-    assert!(messages.is_empty(), "Should discard stale message 0");
+    let delivery = arbiter.receive_message(msg(0, 0)).unwrap();
+    assert!(delivery.messages.is_empty(), "Should discard stale message 0");
 }
 
 #[test]
 fn test_out_of_bounds_source_line() {
-    let mut arbiter = Arbiter::new(2, 5);
+    let mut arbiter = Arbiter::new(2, 5, 1_000_000, 1_000_000, 1024);
     let result = arbiter.receive_message(msg(1, 2)); // source_line 2 is out of bounds for num_lines=2
     assert_eq!(result.unwrap_err(), ArbiterError::OutOfBoundsSourceLine);
 }
 
-#[test]
-fn test_unrecoverable_gap() {
-    let mut arbiter = Arbiter::new(2, 3); // num_lines=2, threshold=3
-
-    // Create a gap at seq_num 1 by receiving messages with higher seq_nums
-    arbiter.receive_message(msg(2, 0)).unwrap();
-    arbiter.receive_message(msg(3, 1)).unwrap();
-
-    // Advance both lines past the unrecoverable threshold for gap 1.
-    // The gap is at seq_num 1. Threshold is 3. Need all lines >= 1 + 3 = 4.
-    arbiter.receive_message(msg(4, 0)).unwrap();
-    let result = arbiter.receive_message(msg(5, 1));
-
-    assert_eq!(result.unwrap_err(), ArbiterError::UnrecoverableGap);
-}
-
 #[test]
 fn test_multiple_gaps() {
-    let mut arbiter = Arbiter::new(1, 10);
+    let mut arbiter = Arbiter::new(1, 10, 1_000_000, 1_000_000, 1024);
     // Create gaps for 1, 3, 4 by sending 2, 5, and 6
-    assert!(arbiter.receive_message(msg(2, 0)).unwrap().is_empty());
-    assert!(arbiter.receive_message(msg(5, 0)).unwrap().is_empty());
-    assert!(arbiter.receive_message(msg(6, 0)).unwrap().is_empty());
+    assert!(arbiter.receive_message(msg(2, 0)).unwrap().messages.is_empty());
+    assert!(arbiter.receive_message(msg(5, 0)).unwrap().messages.is_empty());
+    assert!(arbiter.receive_message(msg(6, 0)).unwrap().messages.is_empty());
 
     // Fill gap for 1, which should release 1 and 2
-    let messages = arbiter.receive_message(msg(1, 0)).unwrap();
-    assert_eq!(messages.len(), 2, "Should return 1 and 2");
-    assert_eq!(messages[0].seq_num, 1);
-    assert_eq!(messages[1].seq_num, 2);
+    let delivery = arbiter.receive_message(msg(1, 0)).unwrap();
+    assert_eq!(delivery.messages.len(), 2, "Should return 1 and 2");
+    assert_eq!(delivery.messages[0].seq_num, 1);
+    assert_eq!(delivery.messages[1].seq_num, 2);
 
     // Fill gap for 3, which should release only 3
-    let messages = arbiter.receive_message(msg(3, 0)).unwrap();
-    assert_eq!(messages.len(), 1, "Should return 3");
-    assert_eq!(messages[0].seq_num, 3);
+    let delivery = arbiter.receive_message(msg(3, 0)).unwrap();
+    assert_eq!(delivery.messages.len(), 1, "Should return 3");
+    assert_eq!(delivery.messages[0].seq_num, 3);
 
     // Fill gap for 4, which should release 3, 4, 5, and 6
-    let messages = arbiter.receive_message(msg(4, 0)).unwrap();
-    assert_eq!(messages.len(), 3, "Should return 4, 5, 6");
-    assert_eq!(messages[0].seq_num, 4);
-    assert_eq!(messages[1].seq_num, 5);
-    assert_eq!(messages[2].seq_num, 6);
+    let delivery = arbiter.receive_message(msg(4, 0)).unwrap();
+    assert_eq!(delivery.messages.len(), 3, "Should return 4, 5, 6");
+    assert_eq!(delivery.messages[0].seq_num, 4);
+    assert_eq!(delivery.messages[1].seq_num, 5);
+    assert_eq!(delivery.messages[2].seq_num, 6);
 }
 
 #[test]
 fn test_duplicate_buffered_message() {
-    let mut arbiter = Arbiter::new(1, 10);
+    let mut arbiter = Arbiter::new(1, 10, 1_000_000, 1_000_000, 1024);
     // Buffer message 3
-    assert!(arbiter.receive_message(msg(3, 0)).unwrap().is_empty());
+    assert!(arbiter.receive_message(msg(3, 0)).unwrap().messages.is_empty());
 
     // Try to buffer a duplicate of message 3
-    assert!(arbiter.receive_message(msg(3, 0)).unwrap().is_empty());
+    assert!(arbiter.receive_message(msg(3, 0)).unwrap().messages.is_empty());
 
     // The BTreeSet in the buffer should have ignored the duplicate.
     // We can't check the buffer size directly, but we can see the output when we fill the gap.
-    let messages = arbiter.receive_message(msg(1, 0)).unwrap();
-    assert_eq!(messages.len(), 1); // Still get 1
-    assert_eq!(messages[0].seq_num, 1);
+    let delivery = arbiter.receive_message(msg(1, 0)).unwrap();
+    assert_eq!(delivery.messages.len(), 1); // Still get 1
+    assert_eq!(delivery.messages[0].seq_num, 1);
 
 
-    let messages = arbiter.receive_message(msg(2, 0)).unwrap();
-    assert_eq!(messages.len(), 2); // Should get 2 and 3, but not two 3s.
-    assert_eq!(messages[0].seq_num, 2);
-    assert_eq!(messages[1].seq_num, 3);
+    let delivery = arbiter.receive_message(msg(2, 0)).unwrap();
+    assert_eq!(delivery.messages.len(), 2); // Should get 2 and 3, but not two 3s.
+    assert_eq!(delivery.messages[0].seq_num, 2);
+    assert_eq!(delivery.messages[1].seq_num, 3);
 }
 
 #[test]
 fn test_unrecoverable_gap_threshold_not_met() {
-    let mut arbiter = Arbiter::new(2, 5); // threshold = 5
+    let mut arbiter = Arbiter::new(2, 5, 1_000_000, 1_000_000, 1024); // threshold = 5
 
     // Create a gap at seq_num 1
     arbiter.receive_message(msg(2, 0)).unwrap();
 
-    // Advance lines, but not enough to trigger the error.
-    // Gap is at 1, threshold is 5. Error triggers when all lines >= 1 + 5 = 6.
+    // Advance lines, but not enough to trigger recovery.
+    // Gap is at 1, threshold is 5. Recovery triggers when all lines >= 1 + 5 = 6.
     arbiter.receive_message(msg(5, 0)).unwrap(); // Line 0 is at 5
-    let messages = arbiter.receive_message(msg(4, 1)).unwrap(); // Line 1 is at 4
+    let delivery = arbiter.receive_message(msg(4, 1)).unwrap(); // Line 1 is at 4
 
-    // No error should occur, and no messages should be released.
-    assert!(messages.is_empty());
+    // No recovery should occur yet, and no messages should be released.
+    assert!(delivery.messages.is_empty());
+    assert!(delivery.lost.is_empty());
 }
 
 #[test]
 fn test_recovery_from_unrecoverable_gap() {
-    let mut arbiter = Arbiter::new(2, 3); // threshold = 3
+    let mut arbiter = Arbiter::new(2, 3, 1_000_000, 1_000_000, 1024); // threshold = 3
 
     // Create a gap at seq_num 1
     arbiter.receive_message(msg(2, 0)).unwrap();
     arbiter.receive_message(msg(5, 1)).unwrap(); // Buffer msg 5
 
-    // Trigger the unrecoverable gap error.
-    // Gap is at 1, threshold is 3. Error triggers when all lines >= 1 + 3 = 4.
-    let result = arbiter.receive_message(msg(4, 0));
-    assert_eq!(result.unwrap_err(), ArbiterError::UnrecoverableGap);
+    // All lines pass the gap at seq_num 1 (threshold 3, so all lines >= 1 + 3 = 4):
+    // the arbiter self-heals instead of getting stuck, skipping 1 and releasing 2.
+    let delivery = arbiter.receive_message(msg(4, 0)).unwrap();
+    assert_eq!(delivery.lost, vec![1]);
+    assert_eq!(delivery.messages.len(), 1);
+    assert_eq!(delivery.messages[0].seq_num, 2);
+
+    // The arbiter keeps working afterwards: the next in-order message releases itself
+    // plus the previously buffered 4 and 5.
+    let delivery = arbiter.receive_message(msg(3, 1)).unwrap();
+    assert_eq!(delivery.messages.len(), 3, "Should return 3, 4, and 5");
+    assert_eq!(delivery.messages[0].seq_num, 3);
+    assert_eq!(delivery.messages[1].seq_num, 4);
+    assert_eq!(delivery.messages[2].seq_num, 5);
+}
+
+#[test]
+fn test_tick_expires_stale_gap() {
+    let mut arbiter = Arbiter::new(1, 10, 100, 1_000_000, 1024); // latency_us = 100
+
+    // Message 3 arrives at t=0, but 1 and 2 are missing.
+    assert!(arbiter
+        .receive_message(msg_at(3, 0, 0))
+        .unwrap()
+        .messages
+        .is_empty());
+
+    // Not enough time has passed yet; the gap should remain open.
+    assert!(arbiter.tick(50).is_empty());
+
+    // Past the latency budget: the gap is abandoned and message 3 is released.
+    let messages = arbiter.tick(101);
+    assert_eq!(messages.len(), 1);
+    assert_eq!(messages[0].seq_num, 3);
+
+    // A late fill for the abandoned range is now stale and discarded.
+    let delivery = arbiter.receive_message(msg_at(2, 0, 101)).unwrap();
+    assert!(delivery.messages.is_empty());
+}
+
+#[test]
+fn test_tick_expires_multiple_consecutive_gaps() {
+    let mut arbiter = Arbiter::new(1, 10, 100, 1_000_000, 1024); // latency_us = 100
+
+    // Two separate gaps: missing 1-2 before message 3, and missing 4 before message 5.
+    arbiter.receive_message(msg_at(3, 0, 0)).unwrap();
+    arbiter.receive_message(msg_at(5, 0, 0)).unwrap();
+
+    let messages = arbiter.tick(200);
+    assert_eq!(messages.len(), 2, "Should release both 3 and 5");
+    assert_eq!(messages[0].seq_num, 3);
+    assert_eq!(messages[1].seq_num, 5);
+}
+
+#[test]
+fn test_dead_line_excluded_from_unrecoverable_quorum() {
+    let mut arbiter = Arbiter::new(2, 3, 1_000_000, 50, 1024); // line_timeout_us = 50
+
+    // Line 1 checks in once at t=0 and then goes silent forever.
+    arbiter.receive_message(msg_at(5, 1, 0)).unwrap();
+    // Line 0 opens a gap at seq_num 1.
+    arbiter.receive_message(msg_at(2, 0, 0)).unwrap();
+    assert!(arbiter.is_line_alive(1, 0));
+
+    // By t=60, line 1 has been silent for 60us, past its 50us timeout.
+    assert!(!arbiter.is_line_alive(1, 60));
+
+    // Line 0 alone passes the gap's threshold (gap 1 + threshold 3 = 4); line 1 is
+    // excluded from the quorum, so the gap resolves on line 0's progress alone.
+    let delivery = arbiter.receive_message(msg_at(4, 0, 60)).unwrap();
+    assert_eq!(delivery.lost, vec![1]);
+    assert_eq!(delivery.messages.len(), 1);
+    assert_eq!(delivery.messages[0].seq_num, 2);
+}
+
+#[test]
+fn test_line_states_reports_liveness() {
+    let mut arbiter = Arbiter::new(2, 10, 1_000_000, 1_000_000, 1024);
+    arbiter.receive_message(msg_at(1, 0, 5)).unwrap();
+    arbiter.receive_message(msg_at(1, 1, 9)).unwrap();
+
+    let states = arbiter.line_states();
+    assert_eq!(states.len(), 2);
+    assert_eq!(states[0].line, 0);
+    assert_eq!(states[0].latest_seq_num, 1);
+    assert_eq!(states[0].last_seen_us, 5);
+    assert_eq!(states[1].line, 1);
+    assert_eq!(states[1].latest_seq_num, 1);
+    assert_eq!(states[1].last_seen_us, 9);
+}
+
+#[test]
+fn test_full_buffer_forces_oldest_gap_and_reports_lagged() {
+    let mut arbiter = Arbiter::new(1, 100, 1_000_000, 1_000_000, 2); // max_buffer_len = 2
+
+    // Fill the buffer to capacity with two future messages.
+    assert!(arbiter.receive_message(msg(3, 0)).unwrap().messages.is_empty());
+    assert!(arbiter.receive_message(msg(5, 0)).unwrap().messages.is_empty());
+
+    // A third future message has no room; the oldest gap (seq 1-2) is sacrificed to
+    // make space, releasing message 3 and reporting how many seq_nums were skipped.
+    let delivery = arbiter.receive_message(msg(7, 0)).unwrap();
+    assert_eq!(delivery.lagged, Some(Lagged { skipped: 2 }));
+    assert_eq!(delivery.messages.len(), 1);
+    assert_eq!(delivery.messages[0].seq_num, 3);
+
+    // The freed-up buffer now holds 5 and 7; filling 4 releases just 4 and 5.
+    let delivery = arbiter.receive_message(msg(4, 0)).unwrap();
+    assert!(delivery.lagged.is_none());
+    assert_eq!(delivery.messages.len(), 2);
+    assert_eq!(delivery.messages[0].seq_num, 4);
+    assert_eq!(delivery.messages[1].seq_num, 5);
+}
+
+#[test]
+fn test_full_buffer_eviction_does_not_buffer_message_older_than_new_floor() {
+    let mut arbiter = Arbiter::new(1, 100, 1_000_000, 1_000_000, 1); // max_buffer_len = 1
+
+    // Buffer holds just seq 5; it's now full.
+    assert!(arbiter.receive_message(msg(5, 0)).unwrap().messages.is_empty());
+
+    // A second future message with no room sacrifices the oldest gap (seq 1-4), which
+    // advances latest_inorder_seq_num to 4 and immediately releases the now-in-order
+    // seq 5 that was sitting in the buffer. That eviction lands the in-order mark past
+    // seq 3's own sequence number, so seq 3 itself must be discarded as stale instead
+    // of being buffered below the new floor (which would otherwise wedge
+    // `process_buffer` forever on a stale entry it can never match).
+    let delivery = arbiter.receive_message(msg(3, 0)).unwrap();
+    assert_eq!(delivery.lagged, Some(Lagged { skipped: 4 }));
+    assert_eq!(delivery.messages.len(), 1);
+    assert_eq!(delivery.messages[0].seq_num, 5);
+    assert_eq!(arbiter.stats().buffer_occupancy, 0);
+
+    // The buffer is empty and healthy again: the next in-order message delivers
+    // normally, proving no stale seq-3 entry was left wedged in the buffer.
+    let delivery = arbiter.receive_message(msg(6, 0)).unwrap();
+    assert!(delivery.lagged.is_none());
+    assert_eq!(delivery.messages.len(), 1);
+    assert_eq!(delivery.messages[0].seq_num, 6);
+}
+
+#[test]
+fn test_zero_buffer_len_delivers_gap_fill_immediately_without_buffering() {
+    let mut arbiter = Arbiter::new(1, 100, 1_000_000, 1_000_000, 0); // max_buffer_len = 0
+
+    // With no room to buffer anything, a future message is delivered immediately and
+    // the intervening sequence numbers are declared lost rather than held open.
+    let delivery = arbiter.receive_message(msg(3, 0)).unwrap();
+    assert_eq!(delivery.lagged, Some(Lagged { skipped: 2 }));
+    assert_eq!(delivery.messages.len(), 1);
+    assert_eq!(delivery.messages[0].seq_num, 3);
+    assert_eq!(arbiter.stats().buffer_occupancy, 0);
+
+    // Arbiter has moved on; the next in-order message delivers normally.
+    let delivery = arbiter.receive_message(msg(4, 0)).unwrap();
+    assert!(delivery.lagged.is_none());
+    assert_eq!(delivery.messages.len(), 1);
+    assert_eq!(delivery.messages[0].seq_num, 4);
+}
 
-}
\ No newline at end of file
+#[test]
+fn test_stats_tracks_cumulative_counters() {
+    let mut arbiter = Arbiter::new(2, 1_000_000, 1_000_000, 1_000_000, 1024);
+
+    arbiter.receive_message(msg(3, 0)).unwrap(); // buffered, opens a gap, depth 3
+    arbiter.receive_message(msg(3, 0)).unwrap(); // duplicate of the buffered entry
+    arbiter.receive_message(msg(1, 1)).unwrap(); // in order, nothing to drain yet
+    arbiter.receive_message(msg(2, 0)).unwrap(); // in order, fills the gap and drains 3
+    arbiter.receive_message(msg(2, 1)).unwrap(); // stale, older than latest_inorder_seq_num
+    arbiter.receive_message(msg(3, 0)).unwrap(); // duplicate of the last delivered message
+
+    let stats = arbiter.stats();
+    assert_eq!(stats.total_received, 6);
+    assert_eq!(stats.received_per_line, vec![4, 2]);
+    assert_eq!(stats.duplicates_discarded, 2);
+    assert_eq!(stats.stale_discarded, 1);
+    assert_eq!(stats.buffered_for_reorder, 1);
+    assert_eq!(stats.gaps_opened, 1);
+    assert_eq!(stats.gaps_filled, 1);
+    assert_eq!(stats.gaps_lost, 0);
+    assert_eq!(stats.max_reorder_depth, 3);
+    assert_eq!(stats.buffer_occupancy, 0);
+
+    arbiter.reset_stats();
+    let stats = arbiter.stats();
+    assert_eq!(stats.total_received, 0);
+    assert_eq!(stats.received_per_line, vec![0, 0]);
+    assert_eq!(stats.max_reorder_depth, 0);
+}
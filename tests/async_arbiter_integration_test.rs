@@ -0,0 +1,52 @@
+// Since this is an integration test in the `tests` directory, it's treated
+// as an external user of the library. We need to import the public items
+// from our `line_arbitration` crate.
+use line_arbitration::async_arbiter::AsyncArbiter;
+use line_arbitration::mytype::message::Message;
+
+// Helper function to create messages for tests
+fn msg(seq_num: u64, source_line: u8) -> Message {
+    Message::new(seq_num, source_line, 0, vec![])
+}
+
+#[tokio::test]
+async fn test_push_fans_out_in_order_stream_to_multiple_subscribers() {
+    let arbiter: AsyncArbiter<Message> = AsyncArbiter::new(1, 100, 1_000_000, 1_000_000, 1024, 16);
+    let mut sub_a = arbiter.subscribe();
+    let mut sub_b = arbiter.subscribe();
+
+    arbiter.push(msg(1, 0)).await.unwrap();
+    arbiter.push(msg(2, 0)).await.unwrap();
+
+    for sub in [&mut sub_a, &mut sub_b] {
+        assert_eq!(sub.recv().await.unwrap().seq_num, 1);
+        assert_eq!(sub.recv().await.unwrap().seq_num, 2);
+    }
+}
+
+#[tokio::test]
+async fn test_subscribe_only_sees_messages_released_after_it_joined() {
+    let arbiter: AsyncArbiter<Message> = AsyncArbiter::new(1, 100, 1_000_000, 1_000_000, 1024, 16);
+
+    arbiter.push(msg(1, 0)).await.unwrap();
+
+    let mut late_sub = arbiter.subscribe();
+    arbiter.push(msg(2, 0)).await.unwrap();
+
+    assert_eq!(late_sub.recv().await.unwrap().seq_num, 2);
+}
+
+#[tokio::test]
+async fn test_slow_subscriber_observes_lagged_error() {
+    // A channel capacity of 1 means a subscriber that doesn't keep up falls behind
+    // after just one extra release.
+    let arbiter: AsyncArbiter<Message> = AsyncArbiter::new(1, 100, 1_000_000, 1_000_000, 1024, 1);
+    let mut slow_sub = arbiter.subscribe();
+
+    arbiter.push(msg(1, 0)).await.unwrap();
+    arbiter.push(msg(2, 0)).await.unwrap();
+    arbiter.push(msg(3, 0)).await.unwrap();
+
+    let err = slow_sub.recv().await.unwrap_err();
+    assert!(matches!(err, tokio::sync::broadcast::error::RecvError::Lagged(_)));
+}